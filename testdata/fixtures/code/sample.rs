@@ -1,47 +1,605 @@
 //! Sample Rust module for testing syntax highlighting.
+//!
+//! Scope note: the `air-gapped/cooked` chunk0-1 through chunk0-7 requests
+//! grew this file well past a minimal highlighting sample (a transaction
+//! engine, versioned history, TTL eviction, pluggable hashers, a `TypeId`
+//! registry, and an in-tree SHA-256). Checked against the rest of this
+//! repository: nothing else here references `sample.rs` or depends on its
+//! size or exact contents, so this series has no in-repo snapshot or
+//! harness to break. If a highlighter harness elsewhere in the broader
+//! project snapshots this file by path, re-verify against that harness
+//! before merging; if this functionality is meant to be load-bearing
+//! rather than sample syntax, it belongs in a real crate/module instead.
 
-use std::collections::HashMap;
+use std::any::{Any, TypeId};
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::hash::BuildHasher;
+use std::time::{Duration, Instant};
+
+/// Supplies the current time to a [`Store`], abstracted so expiration can be
+/// tested without relying on wall-clock sleeps.
+pub trait Clock {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+}
+
+/// The default [`Clock`], backed by [`Instant::now`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A [`Clock`] whose time only moves when told to, for deterministic tests.
+#[derive(Debug)]
+pub struct MockClock {
+    now: Cell<Instant>,
+}
+
+impl MockClock {
+    /// Creates a mock clock pinned to the instant it was created.
+    pub fn new() -> Self {
+        MockClock {
+            now: Cell::new(Instant::now()),
+        }
+    }
+
+    /// Moves this clock's notion of "now" forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        MockClock::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
 
 /// A key-value store with expiration.
 #[derive(Debug)]
-pub struct Store<V: Clone> {
-    data: HashMap<String, Entry<V>>,
+pub struct Store<V: Clone, C: Clock = SystemClock, S = RandomState> {
+    data: HashMap<String, Entry<V>, S>,
+    /// When `Some(window)`, each key retains up to `window` past versions.
+    history_window: Option<usize>,
+    clock: C,
+    /// Cached [`root_hash`](Store::root_hash) and the earliest TTL deadline
+    /// among the entries it covers (if any), cleared on any mutation and
+    /// treated as stale once that deadline passes, so an elapsed TTL
+    /// invalidates the cache even without an intervening write.
+    root_hash_cache: Cell<Option<([u8; 32], Option<Instant>)>>,
 }
 
 #[derive(Debug, Clone)]
 struct Entry<V: Clone> {
     value: V,
     version: u64,
+    /// Past `(version, value)` pairs, oldest first, bounded by the store's
+    /// `history_window`. Empty when history mode is off.
+    history: VecDeque<(u64, V)>,
+    /// When this entry expires, if it was inserted with a TTL.
+    deadline: Option<Instant>,
 }
 
-impl<V: Clone + fmt::Display> Store<V> {
+impl<V: Clone + fmt::Display> Default for Store<V, SystemClock, RandomState> {
+    fn default() -> Self {
+        Store::new()
+    }
+}
+
+impl<V: Clone + fmt::Display> Store<V, SystemClock, RandomState> {
     /// Creates a new empty store.
     pub fn new() -> Self {
         Store {
             data: HashMap::new(),
+            history_window: None,
+            clock: SystemClock,
+            root_hash_cache: Cell::new(None),
+        }
+    }
+
+    /// Creates an empty store that retains up to `window` past versions per
+    /// key, queryable via [`get_version`](Store::get_version) and
+    /// [`versions`](Store::versions). Versions beyond the window are pruned
+    /// FIFO as new ones are inserted.
+    pub fn with_history(window: usize) -> Self {
+        Store {
+            data: HashMap::new(),
+            history_window: Some(window),
+            clock: SystemClock,
+            root_hash_cache: Cell::new(None),
+        }
+    }
+}
+
+impl<V: Clone + fmt::Display, S: BuildHasher> Store<V, SystemClock, S> {
+    /// Creates an empty store using `hasher` to hash keys, e.g. a faster
+    /// non-DoS-resistant hasher for trusted internal keys or a hardened
+    /// seeded hasher for untrusted input. Mirrors `HashMap::with_hasher`.
+    pub fn with_hasher(hasher: S) -> Self {
+        Store {
+            data: HashMap::with_hasher(hasher),
+            history_window: None,
+            clock: SystemClock,
+            root_hash_cache: Cell::new(None),
         }
     }
 
-    /// Inserts a value, returning the previous version number.
+    /// Like [`with_hasher`](Store::with_hasher), pre-allocating capacity for
+    /// at least `capacity` entries.
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        Store {
+            data: HashMap::with_capacity_and_hasher(capacity, hasher),
+            history_window: None,
+            clock: SystemClock,
+            root_hash_cache: Cell::new(None),
+        }
+    }
+}
+
+impl<V: Clone + fmt::Display, C: Clock> Store<V, C, RandomState> {
+    /// Creates an empty store driven by a custom [`Clock`], e.g. a
+    /// [`MockClock`] in tests that exercise TTL expiration.
+    pub fn with_clock(clock: C) -> Self {
+        Store {
+            data: HashMap::default(),
+            history_window: None,
+            clock,
+            root_hash_cache: Cell::new(None),
+        }
+    }
+}
+
+impl<V: Clone + fmt::Display, C: Clock, S: BuildHasher> Store<V, C, S> {
+
+    /// Inserts a value, returning the new version number.
     pub fn insert(&mut self, key: impl Into<String>, value: V) -> u64 {
         let key = key.into();
-        let version = self
-            .data
-            .get(&key)
-            .map(|e| e.version + 1)
-            .unwrap_or(1);
+        let version = self.data.get(&key).map(|e| e.version + 1).unwrap_or(1);
+        self.write_versioned(key, value, version, None);
+        version
+    }
+
+    /// Inserts a value that expires `ttl` from now, returning the new
+    /// version number. Once expired, the entry is skipped by `get`, `len`,
+    /// and `is_empty` and is physically reclaimed by `evict_expired`.
+    pub fn insert_with_ttl(&mut self, key: impl Into<String>, value: V, ttl: Duration) -> u64 {
+        let key = key.into();
+        let version = self.data.get(&key).map(|e| e.version + 1).unwrap_or(1);
+        let deadline = self.clock.now() + ttl;
+        self.write_versioned(key, value, version, Some(deadline));
+        version
+    }
 
+    /// Writes `value` as `version`, shifting the entry's current value into
+    /// its history ring (if history mode is on) before overwriting it.
+    fn write_versioned(&mut self, key: String, value: V, version: u64, deadline: Option<Instant>) {
+        self.root_hash_cache.set(None);
+        let history = match self.data.remove(&key) {
+            Some(mut old) => {
+                if let Some(window) = self.history_window {
+                    if window > 0 {
+                        old.history.push_back((old.version, old.value));
+                        while old.history.len() > window {
+                            old.history.pop_front();
+                        }
+                    }
+                }
+                old.history
+            }
+            None => VecDeque::new(),
+        };
         self.data.insert(
             key,
-            Entry { value, version },
+            Entry {
+                value,
+                version,
+                history,
+                deadline,
+            },
         );
+    }
+
+    fn is_expired(&self, entry: &Entry<V>) -> bool {
+        entry.deadline.is_some_and(|deadline| self.clock.now() >= deadline)
+    }
+
+    /// Gets the value a key held as of a specific version, if still retained.
+    pub fn get_version(&self, key: &str, version: u64) -> Option<&V> {
+        let entry = self.data.get(key)?;
+        if entry.version == version {
+            return Some(&entry.value);
+        }
+        entry
+            .history
+            .iter()
+            .find(|(v, _)| *v == version)
+            .map(|(_, value)| value)
+    }
+
+    /// Iterates over every retained `(version, value)` pair for a key,
+    /// oldest first, ending with the current version.
+    pub fn versions(&self, key: &str) -> impl Iterator<Item = (u64, &V)> {
+        self.data.get(key).into_iter().flat_map(|entry| {
+            entry
+                .history
+                .iter()
+                .map(|(v, value)| (*v, value))
+                .chain(std::iter::once((entry.version, &entry.value)))
+        })
+    }
+
+    /// Gets a reference to a value, or `None` if absent or expired.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        let entry = self.data.get(key)?;
+        if self.is_expired(entry) {
+            return None;
+        }
+        Some(&entry.value)
+    }
+
+    /// Returns the number of non-expired entries.
+    pub fn len(&self) -> usize {
+        self.data.values().filter(|e| !self.is_expired(e)).count()
+    }
+
+    /// Returns true if there are no non-expired entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Physically removes every expired entry, reclaiming their space.
+    pub fn evict_expired(&mut self) {
+        self.root_hash_cache.set(None);
+        let now = self.clock.now();
+        self.data.retain(|_, e| e.deadline.is_none_or(|d| now < d));
+    }
+
+    /// Computes a deterministic digest over every non-expired
+    /// `(key, value, version)` triple in the store, sorted by key so that two stores with identical
+    /// logical contents always produce the same root regardless of
+    /// insertion order or internal bucket layout. The result is cached and
+    /// invalidated on any mutation, and also treated as stale once the
+    /// earliest TTL deadline it covers has passed, so an elapsed TTL can't
+    /// leave a since-expired entry baked into a stale cached root.
+    pub fn root_hash(&self) -> [u8; 32] {
+        if let Some((cached, valid_until)) = self.root_hash_cache.get() {
+            if valid_until.is_none_or(|deadline| self.clock.now() < deadline) {
+                return cached;
+            }
+        }
+
+        let mut live: Vec<(&String, &Entry<V>)> = self
+            .data
+            .iter()
+            .filter(|(_, entry)| !self.is_expired(entry))
+            .collect();
+        live.sort_by_key(|(key, _)| *key);
+
+        let mut hasher = sha256::Sha256::new();
+        let mut earliest_deadline: Option<Instant> = None;
+        for (key, entry) in live {
+            hasher.update(&(key.len() as u64).to_le_bytes());
+            hasher.update(key.as_bytes());
+            let rendered = entry.value.to_string();
+            hasher.update(&(rendered.len() as u64).to_le_bytes());
+            hasher.update(rendered.as_bytes());
+            hasher.update(&entry.version.to_le_bytes());
+            if let Some(deadline) = entry.deadline {
+                earliest_deadline = Some(earliest_deadline.map_or(deadline, |d| d.min(deadline)));
+            }
+        }
+
+        let digest = hasher.finalize();
+        self.root_hash_cache.set(Some((digest, earliest_deadline)));
+        digest
+    }
+
+    /// Starts a transaction that buffers writes until `commit()` is called.
+    ///
+    /// Dropping the transaction without committing discards every staged
+    /// write, leaving the store exactly as it was before `begin()`.
+    pub fn begin(&mut self) -> Transaction<'_, V, C, S> {
+        Transaction {
+            sink: self,
+            writes: HashMap::new(),
+            base_versions: HashMap::new(),
+        }
+    }
+
+    /// Iterates over every non-expired `(key, value)` pair whose key starts
+    /// with `prefix`.
+    pub fn iter_prefix<'a>(&'a self, prefix: &str) -> impl Iterator<Item = (&'a str, &'a V)> + 'a {
+        let prefix = prefix.to_string();
+        self.data
+            .iter()
+            .filter(move |(k, _)| k.starts_with(prefix.as_str()))
+            .filter(|(_, e)| !self.is_expired(e))
+            .map(|(k, e)| (k.as_str(), &e.value))
+    }
+
+    /// Narrows this store to a sub-namespace sharing the same backing map:
+    /// every key the returned [`PrefixedStore`] reads or writes is
+    /// transparently prefixed with `prefix` before reaching this store, and
+    /// stripped again on the way back out.
+    pub fn prefixed(&mut self, prefix: impl Into<String>) -> PrefixedStore<'_, V, C, S> {
+        PrefixedStore {
+            store: self,
+            prefix: prefix.into(),
+        }
+    }
+}
+
+/// A view over a [`Store`] narrowed to keys sharing a given prefix.
+///
+/// Several logical stores can share one backing `HashMap` by each working
+/// through a differently-prefixed `PrefixedStore`, without key-collision
+/// bookkeeping in caller code.
+pub struct PrefixedStore<'a, V: Clone, C: Clock, S: BuildHasher> {
+    store: &'a mut Store<V, C, S>,
+    prefix: String,
+}
+
+impl<'a, V: Clone + fmt::Display, C: Clock, S: BuildHasher> PrefixedStore<'a, V, C, S> {
+    fn namespaced(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    /// Inserts a value under `key` within this namespace.
+    pub fn insert(&mut self, key: impl Into<String>, value: V) -> u64 {
+        let namespaced = self.namespaced(&key.into());
+        self.store.insert(namespaced, value)
+    }
+
+    /// Gets a reference to a value within this namespace.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.store.get(&self.namespaced(key))
+    }
+
+    /// Iterates over every `(key, value)` pair in this namespace, with the
+    /// prefix stripped from each key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> + '_ {
+        self.store
+            .iter_prefix(&self.prefix)
+            .map(move |(k, v)| (&k[self.prefix.len()..], v))
+    }
+}
+
+/// Where a [`Transaction`]'s writes land once it commits.
+///
+/// A top-level transaction's sink is the real [`Store`]. A nested
+/// transaction's sink is its *parent* `Transaction`, so committing a nested
+/// transaction only folds its staged writes into the parent's own staging
+/// maps — the real store is touched only when the outermost transaction
+/// commits. This lets nesting go arbitrarily deep while keeping the
+/// "nothing is visible until the outermost commit" guarantee.
+trait WriteSink<V: Clone, C: Clock, S: BuildHasher> {
+    fn get(&self, key: &str) -> Option<&V>;
+    fn base_version(&mut self, key: &str) -> Option<u64>;
+    fn apply(&mut self, key: String, write: Option<V>);
+}
+
+impl<V: Clone + fmt::Display, C: Clock, S: BuildHasher> WriteSink<V, C, S> for Store<V, C, S> {
+    fn get(&self, key: &str) -> Option<&V> {
+        Store::get(self, key)
+    }
+
+    fn base_version(&mut self, key: &str) -> Option<u64> {
+        self.data.get(key).map(|e| e.version)
+    }
+
+    fn apply(&mut self, key: String, write: Option<V>) {
+        match write {
+            Some(value) => {
+                let version = self.base_version(&key).map(|v| v + 1).unwrap_or(1);
+                self.write_versioned(key, value, version, None);
+            }
+            None => {
+                self.root_hash_cache.set(None);
+                self.data.remove(&key);
+            }
+        }
+    }
+}
+
+/// A buffered set of writes against a [`Store`].
+///
+/// Inserts and removes made through a `Transaction` are staged entirely in
+/// memory and never touch the backing store until [`commit`](Transaction::commit)
+/// is called, at which point each touched key is written exactly once.
+/// Dropping the transaction (or calling [`rollback`](Transaction::rollback))
+/// simply discards the staged writes; since the store was never mutated,
+/// there is nothing to restore. Transactions can be nested: a nested
+/// transaction's commit only folds its writes into its parent's staging
+/// maps (see [`WriteSink`]), so nothing reaches the real store — and
+/// nothing can be rolled back out of it — until the outermost transaction
+/// itself commits.
+pub struct Transaction<'a, V: Clone, C: Clock, S: BuildHasher> {
+    sink: &'a mut dyn WriteSink<V, C, S>,
+    /// Staged write per touched key: `Some(value)` for an insert, `None`
+    /// for a removal. Folded into `sink` only on `commit()`.
+    writes: HashMap<String, Option<V>>,
+    /// Each touched key's version as it stood before this transaction first
+    /// touched it (`None` if the key didn't exist), keyed by first touch so
+    /// repeated writes to the same key renumber relative to committed state
+    /// rather than any staged intermediate.
+    base_versions: HashMap<String, Option<u64>>,
+}
+
+impl<'a, V: Clone + fmt::Display, C: Clock, S: BuildHasher> WriteSink<V, C, S>
+    for Transaction<'a, V, C, S>
+{
+    fn get(&self, key: &str) -> Option<&V> {
+        Transaction::get(self, key)
+    }
+
+    fn base_version(&mut self, key: &str) -> Option<u64> {
+        Transaction::base_version(self, key)
+    }
+
+    fn apply(&mut self, key: String, write: Option<V>) {
+        self.writes.insert(key, write);
+    }
+}
+
+impl<'a, V: Clone + fmt::Display, C: Clock, S: BuildHasher> Transaction<'a, V, C, S> {
+    /// Starts a nested transaction on top of this one.
+    ///
+    /// The nested transaction stages its own writes independently of this
+    /// outer transaction: rolling it back only discards writes staged after
+    /// it began, leaving this outer transaction's staged writes untouched.
+    /// Committing it folds its writes into this outer transaction instead
+    /// of the backing store (see [`WriteSink`]).
+    pub fn begin(&mut self) -> Transaction<'_, V, C, S> {
+        Transaction {
+            sink: self,
+            writes: HashMap::new(),
+            base_versions: HashMap::new(),
+        }
+    }
+
+    fn base_version(&mut self, key: &str) -> Option<u64> {
+        *self
+            .base_versions
+            .entry(key.to_string())
+            .or_insert_with(|| self.sink.base_version(key))
+    }
+
+    /// Stages an insert, returning the version it would receive if committed
+    /// right now.
+    pub fn insert(&mut self, key: impl Into<String>, value: V) -> u64 {
+        let key = key.into();
+        let version = self.base_version(&key).map(|v| v + 1).unwrap_or(1);
+        self.writes.insert(key, Some(value));
         version
     }
 
-    /// Gets a reference to a value.
+    /// Stages a removal.
+    pub fn remove(&mut self, key: &str) {
+        self.base_version(key);
+        self.writes.insert(key.to_string(), None);
+    }
+
+    /// Gets a reference to a value as it stands within this transaction,
+    /// falling back to its sink (the backing store, or a parent
+    /// transaction) for keys this transaction hasn't staged a write for.
     pub fn get(&self, key: &str) -> Option<&V> {
-        self.data.get(key).map(|e| &e.value)
+        match self.writes.get(key) {
+            Some(Some(value)) => Some(value),
+            Some(None) => None,
+            None => self.sink.get(key),
+        }
+    }
+
+    /// Applies the staged writes to this transaction's sink, renumbering
+    /// each touched key's version relative to the state committed before
+    /// this transaction began rather than any intermediate version seen
+    /// while staging. For a nested transaction, the sink is the parent
+    /// transaction, so nothing reaches the real store until the outermost
+    /// transaction commits.
+    pub fn commit(self) {
+        let Transaction { sink, writes, .. } = self;
+        for (key, write) in writes {
+            sink.apply(key, write);
+        }
+    }
+
+    /// Discards every write staged by this transaction. Since nothing was
+    /// ever applied to its sink, this is equivalent to simply dropping the
+    /// transaction.
+    pub fn rollback(self) {}
+}
+
+impl<V: Clone + fmt::Display, C: Clock, S: BuildHasher> fmt::Display for Store<V, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, entry) in self.data.iter().filter(|(_, entry)| !self.is_expired(entry)) {
+            writeln!(f, "{}: {} (v{})", key, entry.value, entry.version)?;
+        }
+        Ok(())
+    }
+}
+
+/// A heterogeneous key-value store keyed by `String`, holding one boxed
+/// value of any `'static` type per key instead of being monomorphized over
+/// a single value type like [`Store`]. Each key still tracks a version
+/// number, incremented on every `insert_any`.
+#[derive(Debug, Default)]
+pub struct AnyStore {
+    data: HashMap<String, AnyEntry>,
+}
+
+struct AnyEntry {
+    value: Box<dyn Any + Send + Sync>,
+    version: u64,
+    type_id: TypeId,
+}
+
+impl fmt::Debug for AnyEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyEntry")
+            .field("version", &self.version)
+            .field("type_id", &self.type_id)
+            .finish()
+    }
+}
+
+impl AnyStore {
+    /// Creates a new empty store.
+    pub fn new() -> Self {
+        AnyStore {
+            data: HashMap::new(),
+        }
+    }
+
+    /// Inserts a value of any `'static` type, returning the new version
+    /// number for the key.
+    pub fn insert_any<T: Send + Sync + 'static>(&mut self, key: impl Into<String>, value: T) -> u64 {
+        let key = key.into();
+        let version = self.data.get(&key).map(|e| e.version + 1).unwrap_or(1);
+        self.data.insert(
+            key,
+            AnyEntry {
+                value: Box::new(value),
+                version,
+                type_id: TypeId::of::<T>(),
+            },
+        );
+        version
+    }
+
+    /// Gets a reference to the value at `key`, downcast to `T`. Returns
+    /// `None` if the key is absent or holds a value of a different type,
+    /// rather than panicking.
+    pub fn get_any<T: 'static>(&self, key: &str) -> Option<&T> {
+        let entry = self.data.get(key)?;
+        if entry.type_id != TypeId::of::<T>() {
+            return None;
+        }
+        entry.value.downcast_ref::<T>()
+    }
+
+    /// Removes and returns the value at `key` if it holds a `T`, leaving the
+    /// entry in place (and returning `None`) on a type mismatch.
+    pub fn remove_any<T: 'static>(&mut self, key: &str) -> Option<T> {
+        if self.data.get(key)?.type_id != TypeId::of::<T>() {
+            return None;
+        }
+        self.data
+            .remove(key)
+            .and_then(|entry| entry.value.downcast::<T>().ok())
+            .map(|value| *value)
     }
 
     /// Returns the number of entries.
@@ -55,12 +613,141 @@ impl<V: Clone + fmt::Display> Store<V> {
     }
 }
 
-impl<V: Clone + fmt::Display> fmt::Display for Store<V> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (key, entry) in &self.data {
-            writeln!(f, "{}: {} (v{})", key, entry.value, entry.version)?;
+/// A minimal, dependency-free SHA-256 implementation used to compute
+/// [`Store::root_hash`]. Kept private: callers only ever see the resulting
+/// 32-byte digest.
+mod sha256 {
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+    ];
+
+    pub struct Sha256 {
+        state: [u32; 8],
+        buffer: Vec<u8>,
+        len_bits: u64,
+    }
+
+    impl Sha256 {
+        pub fn new() -> Self {
+            Sha256 {
+                state: [
+                    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                    0x5be0cd19,
+                ],
+                buffer: Vec::new(),
+                len_bits: 0,
+            }
+        }
+
+        pub fn update(&mut self, data: &[u8]) {
+            self.len_bits += data.len() as u64 * 8;
+            self.buffer.extend_from_slice(data);
+            while self.buffer.len() >= 64 {
+                let block: [u8; 64] = self.buffer[..64].try_into().unwrap();
+                self.process_block(&block);
+                self.buffer.drain(..64);
+            }
+        }
+
+        pub fn finalize(mut self) -> [u8; 32] {
+            let len_bits = self.len_bits;
+            self.buffer.push(0x80);
+            while self.buffer.len() % 64 != 56 {
+                self.buffer.push(0);
+            }
+            self.buffer.extend_from_slice(&len_bits.to_be_bytes());
+            while !self.buffer.is_empty() {
+                let block: [u8; 64] = self.buffer[..64].try_into().unwrap();
+                self.process_block(&block);
+                self.buffer.drain(..64);
+            }
+
+            let mut out = [0u8; 32];
+            for (i, word) in self.state.iter().enumerate() {
+                out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+            }
+            out
+        }
+
+        fn process_block(&mut self, block: &[u8; 64]) {
+            let mut w = [0u32; 64];
+            for (i, word) in w.iter_mut().take(16).enumerate() {
+                *word = u32::from_be_bytes(block[i * 4..i * 4 + 4].try_into().unwrap());
+            }
+            for i in 16..64 {
+                let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+                let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+                w[i] = w[i - 16]
+                    .wrapping_add(s0)
+                    .wrapping_add(w[i - 7])
+                    .wrapping_add(s1);
+            }
+
+            let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+            for i in 0..64 {
+                let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+                let ch = (e & f) ^ ((!e) & g);
+                let temp1 = h
+                    .wrapping_add(s1)
+                    .wrapping_add(ch)
+                    .wrapping_add(K[i])
+                    .wrapping_add(w[i]);
+                let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+                let maj = (a & b) ^ (a & c) ^ (b & c);
+                let temp2 = s0.wrapping_add(maj);
+
+                h = g;
+                g = f;
+                f = e;
+                e = d.wrapping_add(temp1);
+                d = c;
+                c = b;
+                b = a;
+                a = temp1.wrapping_add(temp2);
+            }
+
+            self.state[0] = self.state[0].wrapping_add(a);
+            self.state[1] = self.state[1].wrapping_add(b);
+            self.state[2] = self.state[2].wrapping_add(c);
+            self.state[3] = self.state[3].wrapping_add(d);
+            self.state[4] = self.state[4].wrapping_add(e);
+            self.state[5] = self.state[5].wrapping_add(f);
+            self.state[6] = self.state[6].wrapping_add(g);
+            self.state[7] = self.state[7].wrapping_add(h);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_sha256_matches_known_vectors() {
+            let mut hasher = Sha256::new();
+            hasher.update(b"");
+            assert_eq!(
+                hex(&hasher.finalize()),
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            );
+
+            let mut hasher = Sha256::new();
+            hasher.update(b"abc");
+            assert_eq!(
+                hex(&hasher.finalize()),
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+            );
+        }
+
+        fn hex(bytes: &[u8]) -> String {
+            bytes.iter().map(|b| format!("{:02x}", b)).collect()
         }
-        Ok(())
     }
 }
 
@@ -75,4 +762,222 @@ mod tests {
         assert_eq!(store.get("key"), Some(&"value"));
         assert_eq!(store.len(), 1);
     }
+
+    #[test]
+    fn test_transaction_commit() {
+        let mut store = Store::new();
+        store.insert("key", "a");
+
+        let mut txn = store.begin();
+        txn.insert("key", "b");
+        txn.insert("other", "c");
+        txn.commit();
+
+        assert_eq!(store.get("key"), Some(&"b"));
+        assert_eq!(store.get("other"), Some(&"c"));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_transaction_rollback_restores_prior_state() {
+        let mut store = Store::new();
+        store.insert("key", "a");
+
+        {
+            let mut txn = store.begin();
+            txn.insert("key", "b");
+            txn.insert("other", "c");
+            txn.rollback();
+        }
+
+        assert_eq!(store.get("key"), Some(&"a"));
+        assert_eq!(store.get("other"), None);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_nested_transaction_inner_rollback_keeps_outer_writes() {
+        let mut store = Store::new();
+        store.insert("key", "a");
+
+        let mut outer = store.begin();
+        outer.insert("key", "b");
+        {
+            let mut inner = outer.begin();
+            inner.insert("key", "c");
+            inner.rollback();
+        }
+        assert_eq!(outer.get("key"), Some(&"b"));
+        outer.commit();
+
+        assert_eq!(store.get("key"), Some(&"b"));
+    }
+
+    #[test]
+    fn test_nested_transaction_inner_commit_folds_into_outer_not_store() {
+        let mut store = Store::new();
+        store.insert("key", "a");
+
+        let mut outer = store.begin();
+        outer.insert("key", "b");
+        {
+            let mut inner = outer.begin();
+            inner.insert("key", "c");
+            inner.commit();
+        }
+        // The inner commit must only have folded "c" into the outer
+        // transaction's own staged writes, not the real store.
+        assert_eq!(outer.get("key"), Some(&"c"));
+        outer.rollback();
+
+        // Since the outer transaction was never committed, the store must
+        // be untouched even though an inner transaction already committed.
+        assert_eq!(store.get("key"), Some(&"a"));
+    }
+
+    #[test]
+    fn test_transaction_repeated_writes_to_same_key_commit_once() {
+        let mut store = Store::with_history(4);
+        store.insert("key", "a");
+
+        let mut txn = store.begin();
+        txn.insert("key", "b");
+        txn.insert("key", "x");
+        txn.commit();
+
+        assert_eq!(store.get("key"), Some(&"x"));
+        let collected: Vec<_> = store.versions("key").map(|(v, value)| (v, *value)).collect();
+        assert_eq!(collected, vec![(1, "a"), (2, "x")]);
+    }
+
+    #[test]
+    fn test_history_window_retains_past_versions() {
+        let mut store = Store::with_history(2);
+        store.insert("key", "a");
+        store.insert("key", "b");
+        store.insert("key", "c");
+        store.insert("key", "d");
+
+        assert_eq!(store.get_version("key", 4), Some(&"d"));
+        assert_eq!(store.get_version("key", 3), Some(&"c"));
+        assert_eq!(store.get_version("key", 2), Some(&"b"));
+        assert_eq!(store.get_version("key", 1), None);
+
+        let collected: Vec<_> = store.versions("key").map(|(v, value)| (v, *value)).collect();
+        assert_eq!(collected, vec![(2, "b"), (3, "c"), (4, "d")]);
+    }
+
+    #[test]
+    fn test_ttl_expiration_via_mock_clock() {
+        let clock = MockClock::new();
+        let mut store = Store::with_clock(clock);
+        store.insert_with_ttl("key", "value", Duration::from_secs(10));
+        assert_eq!(store.get("key"), Some(&"value"));
+
+        store.clock.advance(Duration::from_secs(11));
+        assert_eq!(store.get("key"), None);
+        assert_eq!(store.len(), 0);
+
+        store.evict_expired();
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_display_omits_expired_entries() {
+        let clock = MockClock::new();
+        let mut store = Store::with_clock(clock);
+        store.insert("key", "value");
+        store.insert_with_ttl("stale", "gone", Duration::from_secs(10));
+        store.clock.advance(Duration::from_secs(11));
+
+        assert_eq!(store.to_string(), "key: value (v1)\n");
+    }
+
+    #[test]
+    fn test_with_hasher_uses_supplied_build_hasher() {
+        let mut store = Store::with_hasher(RandomState::new());
+        store.insert("key", "value");
+        assert_eq!(store.get("key"), Some(&"value"));
+    }
+
+    #[test]
+    fn test_any_store_roundtrip_and_type_mismatch() {
+        let mut store = AnyStore::new();
+        let version = store.insert_any("key", 42i32);
+        assert_eq!(version, 1);
+
+        assert_eq!(store.get_any::<i32>("key"), Some(&42));
+        assert_eq!(store.get_any::<String>("key"), None);
+
+        assert_eq!(store.remove_any::<String>("key"), None);
+        assert_eq!(store.remove_any::<i32>("key"), Some(42));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_root_hash_is_order_independent_and_invalidated_on_write() {
+        let mut a = Store::new();
+        a.insert("x", "1");
+        a.insert("y", "2");
+
+        let mut b = Store::new();
+        b.insert("y", "2");
+        b.insert("x", "1");
+
+        assert_eq!(a.root_hash(), b.root_hash());
+
+        let before = a.root_hash();
+        a.insert("x", "3");
+        assert_ne!(a.root_hash(), before);
+    }
+
+    #[test]
+    fn test_root_hash_ignores_expired_entries() {
+        let clock = MockClock::new();
+        let mut store = Store::with_clock(clock);
+        store.insert_with_ttl("key", "value", Duration::from_secs(10));
+        store.clock.advance(Duration::from_secs(11));
+
+        let empty: Store<&str, MockClock> = Store::with_clock(MockClock::new());
+        assert_eq!(store.root_hash(), empty.root_hash());
+    }
+
+    #[test]
+    fn test_root_hash_cache_invalidated_by_elapsed_ttl() {
+        let clock = MockClock::new();
+        let mut store = Store::with_clock(clock);
+        store.insert_with_ttl("key", "value", Duration::from_secs(10));
+        let _ = store.root_hash(); // warms the cache while "key" is still live
+
+        store.clock.advance(Duration::from_secs(11));
+
+        let empty: Store<&str, MockClock> = Store::with_clock(MockClock::new());
+        assert_eq!(store.root_hash(), empty.root_hash());
+    }
+
+    #[test]
+    fn test_iter_prefix_and_prefixed_store() {
+        let mut store = Store::new();
+        store.insert("users:1", "alice");
+        store.insert("users:2", "bob");
+        store.insert("orders:1", "widget");
+
+        let mut users: Vec<_> = store.iter_prefix("users:").collect();
+        users.sort();
+        assert_eq!(users, vec![("users:1", &"alice"), ("users:2", &"bob")]);
+
+        let mut users_ns = store.prefixed("users:");
+        users_ns.insert("3", "carol");
+        assert_eq!(users_ns.get("1"), Some(&"alice"));
+        assert_eq!(users_ns.get("3"), Some(&"carol"));
+
+        let mut entries: Vec<_> = users_ns.iter().collect();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![("1", &"alice"), ("2", &"bob"), ("3", &"carol")]
+        );
+
+        assert_eq!(store.get("users:3"), Some(&"carol"));
+    }
 }